@@ -0,0 +1,122 @@
+// *****************************************************************************************
+// NMEA checksum validation
+// *****************************************************************************************
+//
+// The trailing `*HH` on an NMEA sentence is the hex-encoded XOR of every byte between the
+// leading `$`/`!` and the `*`. Real captures off flaky serial hardware routinely produce
+// truncated or corrupted sentences, so `--checksum` lets the tool react to that instead of
+// silently handing garbage to `Nmea0183Base::from_string`.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChecksumMode {
+    Verify,
+    Drop,
+    Annotate,
+}
+
+impl ChecksumMode {
+    pub fn parse(value: &str) -> Result<ChecksumMode, String> {
+        match value.to_ascii_lowercase().as_str() {
+            "verify" => Ok(ChecksumMode::Verify),
+            "drop" => Ok(ChecksumMode::Drop),
+            "annotate" => Ok(ChecksumMode::Annotate),
+            other => Err(format!(
+                "unknown checksum mode {other:?} (expected verify, drop or annotate)"
+            )),
+        }
+    }
+}
+
+/// Returns `true` if `sentence` carries a valid `*HH` checksum. A sentence with no
+/// leading `$`/`!`, no `*`, or a non-hex/short checksum field is considered invalid.
+pub fn is_valid(sentence: &str) -> bool {
+    let Some(body) = sentence.strip_prefix('$').or_else(|| sentence.strip_prefix('!')) else {
+        return false;
+    };
+    let Some(star) = body.find('*') else {
+        return false;
+    };
+    let (payload, rest) = body.split_at(star);
+    let claimed = &rest.as_bytes()[1..];
+    if claimed.len() < 2 || !claimed[..2].iter().all(u8::is_ascii_hexdigit) {
+        return false;
+    }
+    let Ok(claimed) = u8::from_str_radix(&rest[1..3], 16) else {
+        return false;
+    };
+    let computed = payload.bytes().fold(0u8, |acc, b| acc ^ b);
+    computed == claimed
+}
+
+/// Running good/bad totals, reported at EOF alongside `--count`.
+#[derive(Debug, Default)]
+pub struct ChecksumStats {
+    pub good: u64,
+    pub bad: u64,
+}
+
+impl ChecksumStats {
+    pub fn record(&mut self, valid: bool) {
+        if valid {
+            self.good += 1;
+        } else {
+            self.bad += 1;
+        }
+    }
+
+    pub fn print(&self) {
+        eprintln!("Checksum totals: {} good, {} bad", self.good, self.bad);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn valid_sentence_passes() {
+        assert!(is_valid(
+            "$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*47"
+        ));
+    }
+
+    #[test]
+    fn flipped_checksum_byte_fails() {
+        assert!(!is_valid(
+            "$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*48"
+        ));
+    }
+
+    #[test]
+    fn leading_bang_is_accepted_like_dollar() {
+        assert!(is_valid("!AIVDM,1,1,,A,15M67FC000G?ufbE`FepT@3n00Sb,0*5C"));
+    }
+
+    #[test]
+    fn missing_leading_marker_fails() {
+        assert!(!is_valid("GPGGA,123519*47"));
+    }
+
+    #[test]
+    fn missing_star_fails() {
+        assert!(!is_valid("$GPGGA,123519,4807.038,N"));
+    }
+
+    #[test]
+    fn short_checksum_field_fails() {
+        assert!(!is_valid("$GPGGA,123519*4"));
+    }
+
+    #[test]
+    fn non_hex_checksum_field_fails() {
+        assert!(!is_valid("$GPGGA,123519*ZZ"));
+    }
+
+    #[test]
+    fn mode_parse_is_case_insensitive_and_rejects_unknown() {
+        assert_eq!(ChecksumMode::parse("VERIFY"), Ok(ChecksumMode::Verify));
+        assert_eq!(ChecksumMode::parse("drop"), Ok(ChecksumMode::Drop));
+        assert_eq!(ChecksumMode::parse("Annotate"), Ok(ChecksumMode::Annotate));
+        assert!(ChecksumMode::parse("bogus").is_err());
+    }
+}
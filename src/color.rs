@@ -0,0 +1,69 @@
+// *****************************************************************************************
+// Terminal colorization
+// *****************************************************************************************
+//
+// Modeled on the per-severity color table in Fuchsia's log_listener: a fixed palette of
+// ANSI SGR codes, a stable hash from talker ID to palette slot, and a dedicated highlight
+// for sentences carrying a position fix.
+
+const PALETTE: [&str; 6] = [
+    "\x1b[31m", // red
+    "\x1b[32m", // green
+    "\x1b[33m", // yellow
+    "\x1b[34m", // blue
+    "\x1b[35m", // magenta
+    "\x1b[36m", // cyan
+];
+
+const POSITION_FIX_COLOR: &str = "\x1b[1;37m"; // bold white
+const RESET: &str = "\x1b[0m";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorMode {
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorMode {
+    pub fn parse(value: &str) -> Result<ColorMode, String> {
+        match value.to_ascii_lowercase().as_str() {
+            "auto" => Ok(ColorMode::Auto),
+            "always" => Ok(ColorMode::Always),
+            "never" => Ok(ColorMode::Never),
+            other => Err(format!(
+                "unknown color mode {other:?} (expected auto, always or never)"
+            )),
+        }
+    }
+
+    /// Resolves `auto` against whether stdout is a terminal so piping to a file stays
+    /// clean; `always`/`never` ignore it.
+    pub fn enabled(self, stdout_is_tty: bool) -> bool {
+        match self {
+            ColorMode::Auto => stdout_is_tty,
+            ColorMode::Always => true,
+            ColorMode::Never => false,
+        }
+    }
+}
+
+/// Hashes a talker ID to a stable palette slot so the same talker keeps the same color
+/// for the life of the process.
+fn color_for_talker(talker: &str) -> &'static str {
+    let hash = talker
+        .bytes()
+        .fold(0u32, |acc, b| acc.wrapping_mul(31).wrapping_add(b as u32));
+    PALETTE[hash as usize % PALETTE.len()]
+}
+
+/// Wraps `line` in the color escape for `talker`, or the dedicated position-fix
+/// highlight when `is_position_fix` is set, followed by a reset.
+pub fn colorize(line: &str, talker: &str, is_position_fix: bool) -> String {
+    let color = if is_position_fix {
+        POSITION_FIX_COLOR
+    } else {
+        color_for_talker(talker)
+    };
+    format!("{color}{line}{RESET}")
+}
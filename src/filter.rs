@@ -0,0 +1,49 @@
+// *****************************************************************************************
+// Sentence filters
+// *****************************************************************************************
+//
+// Replaces the old "(a)|(b)|(c)" concatenated-alternation regex with a `RegexSet`, which
+// avoids the catastrophic backtracking a single giant alternation invites on
+// user-supplied patterns and, unlike one fused regex, can tell which pattern matched.
+// "Match everything" (the include default) and "match nothing" (the exclude default) are
+// represented as genuine cases rather than the old ".*" / "^$" placeholder patterns.
+
+use regex::RegexSet;
+
+#[derive(Debug)]
+pub enum Filter {
+    MatchAll,
+    Patterns(RegexSet),
+}
+
+impl Filter {
+    /// Builds an include filter: no patterns (or an empty list) means match everything.
+    pub fn include(patterns: &Option<Vec<String>>) -> Filter {
+        match patterns {
+            None => Filter::MatchAll,
+            Some(patterns) if patterns.is_empty() => Filter::MatchAll,
+            Some(patterns) => Filter::Patterns(Filter::build_set(patterns)),
+        }
+    }
+
+    /// Builds an exclude filter: no patterns (or an empty list) means exclude nothing.
+    pub fn exclude(patterns: &Option<Vec<String>>) -> Filter {
+        match patterns {
+            None => Filter::Patterns(RegexSet::empty()),
+            Some(patterns) if patterns.is_empty() => Filter::Patterns(RegexSet::empty()),
+            Some(patterns) => Filter::Patterns(Filter::build_set(patterns)),
+        }
+    }
+
+    fn build_set(patterns: &[String]) -> RegexSet {
+        RegexSet::new(patterns)
+            .unwrap_or_else(|e| panic!("Could not create regex set for {patterns:?}: {e}"))
+    }
+
+    pub fn is_match(&self, value: &str) -> bool {
+        match self {
+            Filter::MatchAll => true,
+            Filter::Patterns(set) => set.is_match(value),
+        }
+    }
+}
@@ -0,0 +1,46 @@
+use super::{position, OutputFormat};
+use chrono::{DateTime, Utc};
+use libnmea0183::{base::Nmea0183Base, Nmea0183};
+use std::io::{self, Write};
+
+/// Emits a stable column set (`timestamp,talker,message,latitude,longitude,raw`) with a
+/// header row written before the first sentence.
+#[derive(Debug, Default)]
+pub struct CsvFormat {
+    header_written: bool,
+}
+
+impl OutputFormat for CsvFormat {
+    fn write_sentence(
+        &mut self,
+        out: &mut dyn Write,
+        raw_line: &str,
+        base: &Nmea0183Base,
+        classified: &Nmea0183,
+        ts: DateTime<Utc>,
+    ) -> io::Result<()> {
+        if !self.header_written {
+            writeln!(out, "timestamp,talker,message,latitude,longitude,raw")?;
+            self.header_written = true;
+        }
+        let (latitude, longitude) = position(classified);
+        writeln!(
+            out,
+            "{},{},{},{},{},{}",
+            ts.to_rfc3339(),
+            csv_escape(&base.sender),
+            csv_escape(&base.message),
+            latitude.map(|v| v.to_string()).unwrap_or_default(),
+            longitude.map(|v| v.to_string()).unwrap_or_default(),
+            csv_escape(raw_line),
+        )
+    }
+}
+
+fn csv_escape(field: &str) -> String {
+    if field.contains(',') || field.contains('"') {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
@@ -0,0 +1,60 @@
+use super::{position, OutputFormat};
+use chrono::{DateTime, Utc};
+use libnmea0183::{base::Nmea0183Base, Nmea0183};
+use std::io::{self, Write};
+
+struct TrackPoint {
+    latitude: f64,
+    longitude: f64,
+    time: DateTime<Utc>,
+}
+
+/// Accumulates fixes from GGA/GLL/RMC sentences and, at EOF, flushes a single
+/// `<gpx><trk><trkseg>` document with one `<trkpt>` per fix so a whole log becomes one
+/// track that can be dropped straight into mapping tools.
+#[derive(Default)]
+pub struct GpxFormat {
+    points: Vec<TrackPoint>,
+}
+
+impl OutputFormat for GpxFormat {
+    fn write_sentence(
+        &mut self,
+        _out: &mut dyn Write,
+        _raw_line: &str,
+        _base: &Nmea0183Base,
+        classified: &Nmea0183,
+        ts: DateTime<Utc>,
+    ) -> io::Result<()> {
+        if let (Some(latitude), Some(longitude)) = position(classified) {
+            self.points.push(TrackPoint {
+                latitude,
+                longitude,
+                time: ts,
+            });
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self, out: &mut dyn Write) -> io::Result<()> {
+        writeln!(out, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+        writeln!(
+            out,
+            "<gpx version=\"1.1\" creator=\"nmea-logger-rs\" xmlns=\"http://www.topografix.com/GPX/1/1\">"
+        )?;
+        writeln!(out, "  <trk>")?;
+        writeln!(out, "    <trkseg>")?;
+        for point in &self.points {
+            writeln!(
+                out,
+                "      <trkpt lat=\"{}\" lon=\"{}\"><time>{}</time></trkpt>",
+                point.latitude,
+                point.longitude,
+                point.time.to_rfc3339(),
+            )?;
+        }
+        writeln!(out, "    </trkseg>")?;
+        writeln!(out, "  </trk>")?;
+        writeln!(out, "</gpx>")
+    }
+}
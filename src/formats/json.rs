@@ -0,0 +1,40 @@
+use super::{json_escape, position, OutputFormat};
+use chrono::{DateTime, Utc};
+use libnmea0183::{base::Nmea0183Base, Nmea0183};
+use std::io::{self, Write};
+
+/// Emits one JSON object per sentence: talker, message type, the running
+/// `most_recent_timestamp`, whatever position fields `classify` was able to decode, and
+/// the original sentence text under `raw` (mirroring CSV's `raw` column, including any
+/// `--checksum annotate` marker) so sentence
+/// kinds `classify` doesn't decode a position for are still recoverable from the stream.
+#[derive(Debug, Default)]
+pub struct JsonFormat;
+
+impl OutputFormat for JsonFormat {
+    fn write_sentence(
+        &mut self,
+        out: &mut dyn Write,
+        raw_line: &str,
+        base: &Nmea0183Base,
+        classified: &Nmea0183,
+        ts: DateTime<Utc>,
+    ) -> io::Result<()> {
+        let (latitude, longitude) = position(classified);
+        write!(
+            out,
+            "{{\"talker\":\"{}\",\"message\":\"{}\",\"timestamp\":\"{}\"",
+            json_escape(&base.sender),
+            json_escape(&base.message),
+            ts.to_rfc3339(),
+        )?;
+        if let Some(lat) = latitude {
+            write!(out, ",\"latitude\":{lat}")?;
+        }
+        if let Some(lon) = longitude {
+            write!(out, ",\"longitude\":{lon}")?;
+        }
+        write!(out, ",\"raw\":\"{}\"", json_escape(raw_line))?;
+        writeln!(out, "}}")
+    }
+}
@@ -0,0 +1,108 @@
+// *****************************************************************************************
+// Output format subsystem
+// *****************************************************************************************
+//
+// Each `OutputFormat` implementation owns whatever per-run state it needs (a CSV header
+// flag, an accumulated GPX track, ...) and is driven one sentence at a time from
+// `NMEAFile::process_line`. `finish` is called once after the stream is exhausted so
+// formats that emit a trailing document (GPX) get a chance to flush it.
+
+mod csv;
+mod gpx;
+mod json;
+mod raw;
+
+pub use csv::CsvFormat;
+pub use gpx::GpxFormat;
+pub use json::JsonFormat;
+pub use raw::RawFormat;
+
+use chrono::{DateTime, Utc};
+use libnmea0183::{base::Nmea0183Base, Nmea0183};
+use std::io::{self, Write};
+use std::str::FromStr;
+
+pub trait OutputFormat {
+    /// Render one sentence that has already passed the include/exclude filters and the
+    /// start/end time window. `raw_line` is the original sentence text (with a ` *BADCHK*`
+    /// marker appended when `--checksum annotate` flags it), `classified` is
+    /// the result of `libnmea0183::classify` and `ts` is `most_recent_timestamp` at the
+    /// time this sentence was processed.
+    fn write_sentence(
+        &mut self,
+        out: &mut dyn Write,
+        raw_line: &str,
+        base: &Nmea0183Base,
+        classified: &Nmea0183,
+        ts: DateTime<Utc>,
+    ) -> io::Result<()>;
+
+    /// Called once after the input stream is exhausted so formats that buffer state
+    /// (GPX) can flush a trailing document. Most formats have nothing to do here.
+    fn finish(&mut self, _out: &mut dyn Write) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FormatKind {
+    Raw,
+    Json,
+    Csv,
+    Gpx,
+}
+
+impl FormatKind {
+    /// `color_enabled` only affects `raw`; the structured formats ignore it since
+    /// embedding ANSI escapes in JSON/CSV/GPX would break the document they produce.
+    pub fn build(self, color_enabled: bool) -> Box<dyn OutputFormat> {
+        match self {
+            FormatKind::Raw => Box::new(RawFormat::new(color_enabled)),
+            FormatKind::Json => Box::new(JsonFormat::default()),
+            FormatKind::Csv => Box::new(CsvFormat::default()),
+            FormatKind::Gpx => Box::new(GpxFormat::default()),
+        }
+    }
+}
+
+impl FromStr for FormatKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "raw" => Ok(FormatKind::Raw),
+            "json" => Ok(FormatKind::Json),
+            "csv" => Ok(FormatKind::Csv),
+            "gpx" => Ok(FormatKind::Gpx),
+            other => Err(format!(
+                "unknown output format {other:?} (expected raw, json, csv or gpx)"
+            )),
+        }
+    }
+}
+
+/// Best-effort latitude/longitude extraction for the sentence kinds that carry a fix.
+/// Shared by the json, csv and gpx formats so they agree on what counts as "a position".
+pub(crate) fn position(classified: &Nmea0183) -> (Option<f64>, Option<f64>) {
+    match classified {
+        Nmea0183::GGA(sentence) => (sentence.latitude(), sentence.longitude()),
+        Nmea0183::GLL(sentence) => (sentence.latitude(), sentence.longitude()),
+        Nmea0183::RMC(sentence) => (sentence.latitude(), sentence.longitude()),
+        _ => (None, None),
+    }
+}
+
+pub(crate) fn json_escape(s: &str) -> String {
+    let mut result = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            c => result.push(c),
+        }
+    }
+    result
+}
@@ -0,0 +1,41 @@
+use super::OutputFormat;
+use crate::color;
+use chrono::{DateTime, Utc};
+use libnmea0183::{base::Nmea0183Base, Nmea0183};
+use std::io::{self, Write};
+
+/// Passes the original sentence text straight through (plus the ` *BADCHK*` marker under
+/// `--checksum annotate`). This is the tool's
+/// original behaviour, kept as the default so `--format` is opt-in. When `--color` is
+/// enabled, each line is colorized by talker ID, with position-fix sentences (GGA/RMC/
+/// GLL) highlighted separately.
+#[derive(Debug)]
+pub struct RawFormat {
+    color_enabled: bool,
+}
+
+impl RawFormat {
+    pub fn new(color_enabled: bool) -> RawFormat {
+        RawFormat { color_enabled }
+    }
+}
+
+impl OutputFormat for RawFormat {
+    fn write_sentence(
+        &mut self,
+        out: &mut dyn Write,
+        raw_line: &str,
+        base: &Nmea0183Base,
+        classified: &Nmea0183,
+        _ts: DateTime<Utc>,
+    ) -> io::Result<()> {
+        if self.color_enabled {
+            let is_position_fix =
+                matches!(classified, Nmea0183::GGA(_) | Nmea0183::RMC(_) | Nmea0183::GLL(_));
+            let line = color::colorize(raw_line, &base.sender, is_position_fix);
+            writeln!(out, "{line}")
+        } else {
+            writeln!(out, "{raw_line}")
+        }
+    }
+}
@@ -1,10 +1,20 @@
+mod checksum;
+mod color;
+mod filter;
+mod formats;
+mod rotate;
+mod stats;
+
 use chrono::{DateTime, Days, NaiveDateTime, NaiveTime, Utc};
 use clap::Parser;
 use core::fmt::Debug;
+use formats::OutputFormat;
 use libnmea0183::{base::Nmea0183Base, Nmea0183};
 use regex::Regex;
-use std::io::{self, BufRead, BufReader, Write};
+use std::io::{self, BufRead, BufReader, IsTerminal, Write};
 use std::process::exit;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
 use std::{fmt, fs};
 use std::fs::OpenOptions;
 use std::path::Path;
@@ -18,6 +28,19 @@ use std::path::Path;
 struct Cli {
     input_file_name: Option<String>,
 
+    #[arg(
+        long = "color",
+        default_value = "auto",
+        help = "Colorize raw output by talker ID: auto, always, or never"
+    )]
+    color: String,
+
+    #[arg(
+        long = "checksum",
+        help = "Validate NMEA checksums: verify (flag bad sentences to stderr), drop (silently skip them), or annotate (mark bad ones in the output). Unchecked if omitted."
+    )]
+    checksum: Option<String>,
+
     #[arg(
         long = "count",
         short = 'c',
@@ -44,16 +67,30 @@ struct Cli {
     #[arg(
         long = "end",
         short,
-        help = "latest date/time to log in Utc formatted as yyyy-mm-ddThh:mm:ssZ"
+        help = "latest date/time to log: RFC3339, epoch seconds, \"yyyy-mm-dd hh:mm:ss\", \"now\", or a relative window like \"-30m\""
     )]
     end_date: Option<String>,
 
+    #[arg(
+        long = "format",
+        short = 'f',
+        default_value = "raw",
+        help = "Output format: raw, json, csv, or gpx"
+    )]
+    format: String,
+
     #[arg(
     long = "init",
     help = "Initialization data to send to NMEA"
     )]
     initialization : Option<Vec<String>>,
 
+    #[arg(
+        long = "max-files",
+        help = "maximum number of rotated files to keep when --rotate-bytes is set.  Unlimited if omitted."
+    )]
+    max_files: Option<u32>,
+
     #[arg(
         long = "messages",
         short = 'm',
@@ -70,13 +107,33 @@ struct Cli {
     )]
     exclude_messages: Option<Vec<String>>,
 
+    #[arg(
+        long = "output",
+        short = 'o',
+        help = "write output to this file instead of stdout"
+    )]
+    output_file_name: Option<String>,
+
+    #[arg(
+        long = "rotate-bytes",
+        help = "rotate --output once it reaches this many bytes, shifting older files to .1, .2, ..."
+    )]
+    rotate_bytes: Option<u64>,
+
     #[arg(
         long = "start",
         short,
-        help = "earliest date/time to log in Utc formatted as yyyy-mm-ddThh:mm:ssZ"
+        help = "earliest date/time to log: RFC3339, epoch seconds, \"yyyy-mm-dd hh:mm:ss\", \"now\", or a relative window like \"-30m\""
     )]
     start_date: Option<String>,
 
+    #[arg(
+        long = "stats",
+        default_value_t = false,
+        help = "Print a count/first-seen/last-seen/avg-interval table by (talker, message) instead of filtered lines"
+    )]
+    stats: bool,
+
     #[arg(
         long = "termeof",
         default_value_t = false,
@@ -101,13 +158,22 @@ struct NMEAFile {
     start_timestamp: DateTime<Utc>,
     end_timestamp: DateTime<Utc>,
     display_count: bool,
-    include_devices: Regex,
-    exclude_devices: Regex,
-    include_messages: Regex,
-    exclude_messages: Regex,
+    include_devices: filter::Filter,
+    exclude_devices: filter::Filter,
+    include_messages: filter::Filter,
+    exclude_messages: filter::Filter,
     most_recent_timestamp: DateTime<Utc>,
+    date_established: bool,
+    warned_no_date: bool,
     terminate_eof: bool,
     terminate_err: bool,
+    format: Box<dyn OutputFormat>,
+    stats: Option<Arc<Mutex<stats::StatsCollector>>>,
+    output_file_name: Option<String>,
+    rotate_bytes: Option<u64>,
+    max_files: Option<u32>,
+    checksum_mode: Option<checksum::ChecksumMode>,
+    checksum_stats: checksum::ChecksumStats,
 }
 
 impl NMEAFile {
@@ -147,44 +213,14 @@ impl NMEAFile {
             }
         };
 
-        let binding_default = Some(vec![String::from(".*")]);
-        let include_devices = {
-            let binding_local = &(cli.include_devices.clone());
-            NMEAFile::create_regex(if cli.include_devices.is_some() {
-                &binding_local
-            } else {
-                &binding_default
-            })
-        };
-
-        let include_messages = {
-            let binding_local = cli.include_messages.clone();
-            NMEAFile::create_regex(if cli.include_messages.is_some() {
-                &binding_local
-            } else {
-                &binding_default
-            })
-        };
-
-        let binding_default = Some(vec![String::from("^$")]);
-        let exclude_devices = {
-            let binding_local = cli.exclude_devices.clone();
-            NMEAFile::create_regex(if cli.exclude_devices.is_some() {
-                &binding_local
-            } else {
-                &binding_default
-            })
-        };
-
-        let exclude_messages = {
-            let binding_local = cli.exclude_messages.clone();
-            NMEAFile::create_regex(if cli.exclude_messages.is_some() {
-                &binding_local
-            } else {
-                &binding_default
-            })
-        };
+        let include_devices = filter::Filter::include(&cli.include_devices);
+        let include_messages = filter::Filter::include(&cli.include_messages);
+        let exclude_devices = filter::Filter::exclude(&cli.exclude_devices);
+        let exclude_messages = filter::Filter::exclude(&cli.exclude_messages);
 
+        // Placeholder until an RMC/ZDA sentence (the only kinds that carry a full date)
+        // is seen; GGA/GLL-only input never replaces the date half of this, which is why
+        // `date_established` exists to flag output built entirely from this placeholder.
         let most_recent_timestamp = {
             let current_timestamp = Utc::now();
             let current_date = current_timestamp.date_naive();
@@ -198,10 +234,10 @@ impl NMEAFile {
                 .unwrap()
                 .with_timezone(&Utc)
         } else {
-            match DateTime::parse_from_rfc3339(cli.start_date.clone().unwrap().as_str()) {
-                Ok(d) => d.with_timezone(&Utc),
+            match NMEAFile::parse_bound_time(cli.start_date.clone().unwrap().as_str()) {
+                Ok(d) => d,
                 Err(e) => {
-                    eprintln!("{e:?}");
+                    eprintln!("{e}");
                     exit(-1);
                 }
             }
@@ -212,16 +248,16 @@ impl NMEAFile {
                 .unwrap()
                 .with_timezone(&Utc)
         } else {
-            match DateTime::parse_from_rfc3339(cli.end_date.clone().unwrap().as_str()) {
-                Ok(d) => d.with_timezone(&Utc),
+            match NMEAFile::parse_bound_time(cli.end_date.clone().unwrap().as_str()) {
+                Ok(d) => d,
                 Err(e) => {
-                    eprintln!("{e:?}");
+                    eprintln!("{e}");
                     exit(-1);
                 }
             }
         };
 
-        println!("Start time {:?} End time {:?}", start_timestamp, end_timestamp);
+        eprintln!("Start time {:?} End time {:?}", start_timestamp, end_timestamp);
         if start_timestamp > end_timestamp {
             eprintln!("Start time is after or the same as end time.");
             exit(-1);
@@ -229,6 +265,62 @@ impl NMEAFile {
 
         let display_count = cli.display_count;
 
+        let color_mode = match color::ColorMode::parse(cli.color.as_str()) {
+            Ok(mode) => mode,
+            Err(e) => {
+                eprintln!("{e}");
+                exit(-1);
+            }
+        };
+        // The sentence sink is stdout only when `--output` isn't given (see
+        // `build_output`); a file is never a terminal, so `auto` must not look at the
+        // real stdout once output has been redirected to one.
+        let sink_is_tty = cli.output_file_name.is_none() && io::stdout().is_terminal();
+        let color_enabled = color_mode.enabled(sink_is_tty);
+
+        let format = match formats::FormatKind::from_str(cli.format.as_str()) {
+            Ok(kind) => kind.build(color_enabled),
+            Err(e) => {
+                eprintln!("{e}");
+                exit(-1);
+            }
+        };
+
+        // --stats profiles a live bus, which is normally interrupted with Ctrl-C rather
+        // than run to EOF, so the collected table also has to be flushed from a SIGINT
+        // handler, not just the end of `process`.
+        let stats = if cli.stats {
+            let collector = Arc::new(Mutex::new(stats::StatsCollector::new()));
+            let handler_collector = Arc::clone(&collector);
+            if let Err(e) = ctrlc::set_handler(move || {
+                if let Ok(stats) = handler_collector.lock() {
+                    let _ = stats.print(&mut io::stdout());
+                }
+                exit(0);
+            }) {
+                eprintln!("Could not install SIGINT handler for --stats: {e:?}");
+            }
+            Some(collector)
+        } else {
+            None
+        };
+
+        if cli.rotate_bytes.is_some() && cli.output_file_name.is_none() {
+            eprintln!("--rotate-bytes requires --output");
+            exit(-1);
+        }
+
+        let checksum_mode = match &cli.checksum {
+            None => None,
+            Some(value) => match checksum::ChecksumMode::parse(value) {
+                Ok(mode) => Some(mode),
+                Err(e) => {
+                    eprintln!("{e}");
+                    exit(-1);
+                }
+            },
+        };
+
         Some(NMEAFile {
             stream: reader,
             start_timestamp,
@@ -239,55 +331,121 @@ impl NMEAFile {
             include_messages,
             exclude_messages,
             most_recent_timestamp,
+            date_established: false,
+            warned_no_date: false,
             terminate_eof: cli.terminate_on_eof,
             terminate_err: cli.terminate_on_err,
+            format,
+            stats,
+            output_file_name: cli.output_file_name.clone(),
+            rotate_bytes: cli.rotate_bytes,
+            max_files: cli.max_files,
+            checksum_mode,
+            checksum_stats: checksum::ChecksumStats::default(),
         })
     }
 
-    fn create_regex(patterns: &Option<Vec<String>>) -> regex::Regex {
-        let mut result = String::new();
-        match patterns {
-            None => regex::Regex::new("").expect("Could not create default include/exclude regex."),
-            Some(patterns) => {
-                for pattern in patterns {
-                    if !result.is_empty() {
-                        result.push('|');
-                    };
-                    result.push('(');
-                    result.push_str(pattern);
-                    result.push(')');
-                }
-                regex::Regex::new(result.as_str())
-                    .expect(format!("Could not create regex for {:?}", patterns).as_str())
+    /// Builds the sink sentences, stats and GPX/CSV documents are written to: the file
+    /// named by `--output` (rotating it once `--rotate-bytes` is crossed), or stdout.
+    fn build_output(&self) -> Box<dyn Write> {
+        match &self.output_file_name {
+            None => Box::new(io::stdout()),
+            Some(path) => match self.rotate_bytes {
+                None => match OpenOptions::new().create(true).append(true).open(path) {
+                    Ok(file) => Box::new(file),
+                    Err(e) => {
+                        eprintln!("Could not open {path}: {e:?}");
+                        exit(-1);
+                    }
+                },
+                Some(capacity) => match rotate::RotatingWriter::new(path, capacity, self.max_files) {
+                    Ok(writer) => Box::new(writer),
+                    Err(e) => {
+                        eprintln!("Could not open {path}: {e:?}");
+                        exit(-1);
+                    }
+                },
+            },
+        }
+    }
+
+    /// Parses a `--start`/`--end` bound, trying each supported form in turn: `now` or a
+    /// relative window like `-30m`/`-2h`/`-1d`/`-45s` (subtracted from `Utc::now()`),
+    /// RFC3339 as before, a bare Unix epoch seconds integer, and the plain
+    /// `YYYY-MM-DD HH:MM:SS` form (assumed UTC). Marine loggers and spreadsheets rarely
+    /// hand you a clean RFC3339 string, so this is more forgiving than the strict parsing
+    /// it replaces.
+    fn parse_bound_time(value: &str) -> Result<DateTime<Utc>, String> {
+        if value == "now" {
+            return Ok(Utc::now());
+        }
+        let relative = Regex::new(r"^-(\d+)([smhd])$").unwrap();
+        if let Some(captures) = relative.captures(value) {
+            let amount: i64 = captures[1]
+                .parse()
+                .map_err(|_| format!("invalid relative time {value:?}"))?;
+            let unit_seconds: i64 = match &captures[2] {
+                "s" => 1,
+                "m" => 60,
+                "h" => 3600,
+                "d" => 86400,
+                _ => unreachable!(),
+            };
+            return Ok(Utc::now() - chrono::Duration::seconds(amount * unit_seconds));
+        }
+        if let Ok(d) = DateTime::parse_from_rfc3339(value) {
+            return Ok(d.with_timezone(&Utc));
+        }
+        if let Ok(epoch_seconds) = value.parse::<i64>() {
+            if let Some(d) = DateTime::from_timestamp(epoch_seconds, 0) {
+                return Ok(d);
             }
         }
+        if let Ok(naive) = NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S") {
+            return Ok(naive.and_utc());
+        }
+        Err(format!(
+            "Could not parse {value:?} as a date/time (expected \"now\", a relative window like \"-30m\", RFC3339, Unix epoch seconds, or \"YYYY-MM-DD HH:MM:SS\")"
+        ))
     }
 
     fn process(&mut self) {
         let mut buffer = String::new();
         let mut linecount: u128 = 0;
+        let mut out = self.build_output();
         loop {
             buffer.clear();
             match self.stream.read_line(&mut buffer) {
                 Err(e) => {
                     if self.terminate_err {
                         eprintln!("{e:?}");
-                        return;
+                        break;
                     }
                 }
 
                 Ok(n) => {
                     if n == 0 && self.terminate_eof {
-                        return;
+                        break;
                     }
                     if self.display_count {
                         linecount += 1;
                         eprint!("{linecount} \r");
                     }
-                    self.process_line(&buffer);
+                    self.process_line(&buffer, out.as_mut());
                 }
             }
         }
+        if let Some(stats) = &self.stats {
+            if let Err(e) = stats.lock().unwrap().print(out.as_mut()) {
+                eprintln!("{e:?}");
+            }
+        } else if let Err(e) = self.format.finish(out.as_mut()) {
+            eprintln!("{e:?}");
+        }
+        if self.checksum_mode.is_some() {
+            self.checksum_stats.print();
+        }
+        let _ = out.flush();
     }
 
     fn update_time_only(&mut self, timestamp: NaiveTime) {
@@ -301,7 +459,7 @@ impl NMEAFile {
         self.most_recent_timestamp = DateTime::from_naive_utc_and_offset(naive_date_stamp, Utc);
     }
 
-    fn process_line(&mut self, buffer: &String) {
+    fn process_line(&mut self, buffer: &String, out: &mut dyn Write) {
         let mut buffer = buffer.clone();
         match buffer.pop() {
             Some('\n') => {}
@@ -309,6 +467,23 @@ impl NMEAFile {
             _ => {}
         }
         if buffer.len() > 0 {
+            let mut display_line = buffer.clone();
+            if let Some(mode) = self.checksum_mode {
+                let valid = checksum::is_valid(&buffer);
+                self.checksum_stats.record(valid);
+                if !valid {
+                    match mode {
+                        checksum::ChecksumMode::Verify => {
+                            eprintln!(
+                                "Bad checksum ({} so far): {buffer}",
+                                self.checksum_stats.bad
+                            );
+                        }
+                        checksum::ChecksumMode::Drop => return,
+                        checksum::ChecksumMode::Annotate => display_line.push_str(" *BADCHK*"),
+                    }
+                }
+            }
             if let Ok(nmea_base) = Nmea0183Base::from_string(&buffer) {
                 let message = nmea_base.message.as_str();
                 let sender = nmea_base.sender.as_str();
@@ -318,7 +493,8 @@ impl NMEAFile {
                     && self.include_devices.is_match(sender)
                     && !self.exclude_devices.is_match(sender)
                 {
-                    match libnmea0183::classify(nmea_base) {
+                    let classified = libnmea0183::classify(nmea_base.clone());
+                    match &classified {
                         Nmea0183::BWC(sentence) => self.update_time_only(
                             sentence
                                 .timestamp()
@@ -356,7 +532,8 @@ impl NMEAFile {
                         Nmea0183::RMC(sentence) => {
                             self.most_recent_timestamp = sentence
                                 .timestamp()
-                                .expect("Internal error in RMC sentence")
+                                .expect("Internal error in RMC sentence");
+                            self.date_established = true;
                         }
                         Nmea0183::TRF(sentence) => self.update_time_only(
                             sentence
@@ -366,14 +543,34 @@ impl NMEAFile {
                         Nmea0183::ZDA(sentence) => {
                             self.most_recent_timestamp = sentence
                                 .timestamp()
-                                .expect("Internal error in ZDA sentence")
+                                .expect("Internal error in ZDA sentence");
+                            self.date_established = true;
                         }
                         _ => {}
                     }
-                    if self.most_recent_timestamp >= self.start_timestamp
+                    if let Some(stats) = &self.stats {
+                        stats
+                            .lock()
+                            .unwrap()
+                            .record(sender, message, self.most_recent_timestamp);
+                    } else if self.most_recent_timestamp >= self.start_timestamp
                         && self.most_recent_timestamp <= self.end_timestamp
                     {
-                        println!("{buffer}");
+                        if !self.date_established && !self.warned_no_date {
+                            self.warned_no_date = true;
+                            eprintln!(
+                                "Warning: no RMC/ZDA sentence seen yet, so output timestamps carry today's date (process start) rather than the capture date"
+                            );
+                        }
+                        if let Err(e) = self.format.write_sentence(
+                            out,
+                            &display_line,
+                            &nmea_base,
+                            &classified,
+                            self.most_recent_timestamp,
+                        ) {
+                            eprintln!("{e:?}");
+                        }
                     }
                 }
             }
@@ -386,7 +583,7 @@ impl NMEAFile {
 
 impl fmt::Display for NMEAFile {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "NMEAFile {{ stream: <not printable>, start_timestamp: {:?}, end_timestamp: {:?}, include_devices: {:?}, exclude_devices: {:?}, include_messages: {:?}, exclude_messages: {:?} most_recent_time: {:?}, terminate_eof: {:?}, terminate_err: {:?} }}",
+        write!(f, "NMEAFile {{ stream: <not printable>, start_timestamp: {:?}, end_timestamp: {:?}, include_devices: {:?}, exclude_devices: {:?}, include_messages: {:?}, exclude_messages: {:?} most_recent_time: {:?}, terminate_eof: {:?}, terminate_err: {:?}, format: <not printable> }}",
 	       self.start_timestamp, self.end_timestamp, self.include_devices, self.exclude_devices,
 	       self.include_messages, self.exclude_messages, self.most_recent_timestamp,
 	       self.terminate_err, self.terminate_eof)
@@ -404,3 +601,47 @@ fn main() {
         Some(mut n) => n.process(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_rfc3339() {
+        let parsed = NMEAFile::parse_bound_time("2024-03-05T12:30:00Z").unwrap();
+        assert_eq!(parsed.to_rfc3339(), "2024-03-05T12:30:00+00:00");
+    }
+
+    #[test]
+    fn parses_epoch_seconds() {
+        let parsed = NMEAFile::parse_bound_time("1000000000").unwrap();
+        assert_eq!(parsed.timestamp(), 1_000_000_000);
+    }
+
+    #[test]
+    fn parses_plain_date_time() {
+        let parsed = NMEAFile::parse_bound_time("2024-03-05 12:30:00").unwrap();
+        assert_eq!(parsed.to_rfc3339(), "2024-03-05T12:30:00+00:00");
+    }
+
+    #[test]
+    fn parses_now() {
+        let before = Utc::now();
+        let parsed = NMEAFile::parse_bound_time("now").unwrap();
+        let after = Utc::now();
+        assert!(parsed >= before && parsed <= after);
+    }
+
+    #[test]
+    fn parses_relative_window() {
+        let before = Utc::now() - chrono::Duration::seconds(1800);
+        let parsed = NMEAFile::parse_bound_time("-30m").unwrap();
+        let after = Utc::now() - chrono::Duration::seconds(1800);
+        assert!(parsed >= before && parsed <= after);
+    }
+
+    #[test]
+    fn rejects_garbage() {
+        assert!(NMEAFile::parse_bound_time("not a time").is_err());
+    }
+}
@@ -0,0 +1,213 @@
+// *****************************************************************************************
+// Output file rotation
+// *****************************************************************************************
+//
+// Mirrors the DEFAULT_FILE_CAPACITY rotation used by Fuchsia's log_listener: once the
+// current file crosses --rotate-bytes, it is shifted to a numbered suffix (log.nmea ->
+// log.nmea.1 -> log.nmea.2 ...) and a fresh file is opened in its place, so an unattended
+// capture against a live feed never produces one unbounded file.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+pub struct RotatingWriter {
+    path: PathBuf,
+    capacity: u64,
+    max_files: Option<u32>,
+    file: File,
+    bytes_written: u64,
+    // `JsonFormat`/`CsvFormat` build one record via several `write!`/`writeln!` calls, so
+    // testing capacity on every `write()` could rotate mid-record and split one JSON
+    // object or CSV row across the old and new file. Buffering until a `\n` lets rotation
+    // only ever land between complete records.
+    pending: Vec<u8>,
+}
+
+impl RotatingWriter {
+    pub fn new(
+        path: impl Into<PathBuf>,
+        capacity: u64,
+        max_files: Option<u32>,
+    ) -> io::Result<RotatingWriter> {
+        let path = path.into();
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let bytes_written = file.metadata()?.len();
+        Ok(RotatingWriter {
+            path,
+            capacity,
+            max_files,
+            file,
+            bytes_written,
+            pending: Vec::new(),
+        })
+    }
+
+    /// Rotates (if the pending line would cross capacity) and writes one complete line
+    /// (including its trailing `\n`, if any) straight to the underlying file.
+    fn write_complete_line(&mut self, line: &[u8]) -> io::Result<()> {
+        if self.bytes_written + line.len() as u64 > self.capacity {
+            self.rotate()?;
+        }
+        self.file.write_all(line)?;
+        self.bytes_written += line.len() as u64;
+        Ok(())
+    }
+
+    fn suffixed_path(&self, n: u32) -> PathBuf {
+        let mut name = self.path.clone().into_os_string();
+        name.push(format!(".{n}"));
+        PathBuf::from(name)
+    }
+
+    fn highest_existing_suffix(&self) -> u32 {
+        let mut n = 0;
+        while self.suffixed_path(n + 1).exists() {
+            n += 1;
+        }
+        n
+    }
+
+    /// Shift `path.N` -> `path.N+1` from the highest existing suffix down to 1 (dropping
+    /// anything that would land past `--max-files`), then move `path` itself to `path.1`
+    /// and reopen a fresh, empty `path`. `--max-files 0` means "keep no rotated files at
+    /// all", so it just truncates `path` in place instead of renaming it to `path.1`.
+    fn rotate(&mut self) -> io::Result<()> {
+        if self.max_files == Some(0) {
+            self.file = OpenOptions::new()
+                .create(true)
+                .write(true)
+                .truncate(true)
+                .open(&self.path)?;
+            self.bytes_written = 0;
+            return Ok(());
+        }
+
+        let mut n = self.highest_existing_suffix();
+        while n >= 1 {
+            let from = self.suffixed_path(n);
+            if self.max_files.is_some_and(|max| n >= max) {
+                fs::remove_file(&from).ok();
+            } else {
+                fs::rename(&from, self.suffixed_path(n + 1))?;
+            }
+            n -= 1;
+        }
+        fs::rename(&self.path, self.suffixed_path(1))?;
+        self.file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(&self.path)?;
+        self.bytes_written = 0;
+        Ok(())
+    }
+}
+
+impl Write for RotatingWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.pending.extend_from_slice(buf);
+        while let Some(newline) = self.pending.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=newline).collect();
+            self.write_complete_line(&line)?;
+        }
+        Ok(buf.len())
+    }
+
+    /// Commits any record still pending (a caller that never wrote a trailing `\n`, or
+    /// the last record of the run) straight through without waiting for a newline, then
+    /// flushes the underlying file.
+    fn flush(&mut self) -> io::Result<()> {
+        if !self.pending.is_empty() {
+            let line = std::mem::take(&mut self.pending);
+            self.write_complete_line(&line)?;
+        }
+        self.file.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    static COUNTER: AtomicU32 = AtomicU32::new(0);
+
+    /// A fresh, non-colliding path under the system temp dir, cleaned up on drop.
+    struct TempPath(PathBuf);
+
+    impl TempPath {
+        fn new(test_name: &str) -> TempPath {
+            let unique = COUNTER.fetch_add(1, Ordering::Relaxed);
+            let path = std::env::temp_dir().join(format!(
+                "nmea-logger-rotate-test-{}-{unique}-{test_name}.nmea",
+                std::process::id()
+            ));
+            TempPath(path)
+        }
+    }
+
+    impl Drop for TempPath {
+        fn drop(&mut self) {
+            for n in 0..=3 {
+                let mut name = self.0.clone().into_os_string();
+                if n > 0 {
+                    name.push(format!(".{n}"));
+                }
+                fs::remove_file(PathBuf::from(name)).ok();
+            }
+        }
+    }
+
+    #[test]
+    fn rotates_and_shifts_older_suffixes_up() {
+        let path = TempPath::new("shift");
+        let mut writer = RotatingWriter::new(&path.0, 4, None).unwrap();
+        writer.write_all(b"aaa\n").unwrap(); // fills the first file exactly to capacity
+        writer.write_all(b"bbb\n").unwrap(); // crosses capacity: rotate, then write "bbb\n"
+        writer.write_all(b"ccc\n").unwrap(); // rotate again: .1 -> .2, new .1, then "ccc\n"
+
+        assert_eq!(fs::read_to_string(&path.0).unwrap(), "ccc\n");
+        assert_eq!(fs::read_to_string(path.0.with_extension("nmea.1")).unwrap(), "bbb\n");
+        assert_eq!(fs::read_to_string(path.0.with_extension("nmea.2")).unwrap(), "aaa\n");
+    }
+
+    #[test]
+    fn max_files_drops_the_oldest_suffix() {
+        let path = TempPath::new("max-files");
+        let mut writer = RotatingWriter::new(&path.0, 4, Some(1)).unwrap();
+        writer.write_all(b"aaa\n").unwrap();
+        writer.write_all(b"bbb\n").unwrap(); // rotate: aaa\n -> .1
+        writer.write_all(b"ccc\n").unwrap(); // rotate: .1 (aaa\n) would become .2 but max-files=1 drops it
+
+        assert_eq!(fs::read_to_string(&path.0).unwrap(), "ccc\n");
+        assert_eq!(fs::read_to_string(path.0.with_extension("nmea.1")).unwrap(), "bbb\n");
+        assert!(!path.0.with_extension("nmea.2").exists());
+    }
+
+    #[test]
+    fn max_files_zero_truncates_in_place_without_keeping_any_suffix() {
+        let path = TempPath::new("max-files-zero");
+        let mut writer = RotatingWriter::new(&path.0, 4, Some(0)).unwrap();
+        writer.write_all(b"aaa\n").unwrap();
+        writer.write_all(b"bbb\n").unwrap(); // would rotate, but max-files=0 keeps none
+
+        assert_eq!(fs::read_to_string(&path.0).unwrap(), "bbb\n");
+        assert!(!path.0.with_extension("nmea.1").exists());
+    }
+
+    #[test]
+    fn a_record_split_across_several_writes_is_not_split_by_rotation() {
+        // JsonFormat/CsvFormat build one record via several write!/writeln! calls; a
+        // rotation must never fire between them and split one record across two files.
+        let path = TempPath::new("record-boundary");
+        let mut writer = RotatingWriter::new(&path.0, 6, None).unwrap();
+        writeln!(writer, "aaaa").unwrap(); // one whole record, under capacity
+
+        write!(writer, "bb").unwrap(); // first half of the next record
+        writeln!(writer, "bb").unwrap(); // second half: together they cross capacity
+
+        assert_eq!(fs::read_to_string(&path.0).unwrap(), "bbbb\n");
+        assert_eq!(fs::read_to_string(path.0.with_extension("nmea.1")).unwrap(), "aaaa\n");
+    }
+}
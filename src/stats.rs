@@ -0,0 +1,81 @@
+// *****************************************************************************************
+// Sentence statistics
+// *****************************************************************************************
+//
+// Backing store for `--stats`: instead of emitting filtered sentences, tally how many of
+// each (talker, message) pair were seen and over what span, so a noisy NMEA bus can be
+// profiled rather than just watched.
+
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+#[derive(Debug, Clone)]
+struct Entry {
+    count: u64,
+    first_seen: DateTime<Utc>,
+    last_seen: DateTime<Utc>,
+}
+
+#[derive(Debug, Default)]
+pub struct StatsCollector {
+    entries: HashMap<(String, String), Entry>,
+}
+
+impl StatsCollector {
+    pub fn new() -> StatsCollector {
+        StatsCollector::default()
+    }
+
+    pub fn record(&mut self, talker: &str, message: &str, ts: DateTime<Utc>) {
+        self.entries
+            .entry((talker.to_string(), message.to_string()))
+            .and_modify(|entry| {
+                entry.count += 1;
+                if ts < entry.first_seen {
+                    entry.first_seen = ts;
+                }
+                if ts > entry.last_seen {
+                    entry.last_seen = ts;
+                }
+            })
+            .or_insert(Entry {
+                count: 1,
+                first_seen: ts,
+                last_seen: ts,
+            });
+    }
+
+    /// Print a table sorted by (talker, message) showing count, first/last seen and the
+    /// average interval between sentences for each pair.
+    pub fn print(&self, out: &mut dyn Write) -> io::Result<()> {
+        let mut keys: Vec<&(String, String)> = self.entries.keys().collect();
+        keys.sort();
+
+        writeln!(
+            out,
+            "{:<8}{:<8}{:>10}{:>28}{:>28}{:>16}",
+            "talker", "message", "count", "first_seen", "last_seen", "avg_interval_s"
+        )?;
+        for key in keys {
+            let entry = &self.entries[key];
+            let span = (entry.last_seen - entry.first_seen).num_milliseconds() as f64 / 1000.0;
+            let avg_interval = if entry.count > 1 {
+                span / (entry.count - 1) as f64
+            } else {
+                0.0
+            };
+            writeln!(
+                out,
+                "{:<8}{:<8}{:>10}{:>28}{:>28}{:>16.3}",
+                key.0,
+                key.1,
+                entry.count,
+                entry.first_seen.to_rfc3339(),
+                entry.last_seen.to_rfc3339(),
+                avg_interval
+            )?;
+        }
+        Ok(())
+    }
+}